@@ -1,3 +1,6 @@
+mod projection;
+mod ws;
+
 use colorgrad::{Color, CustomGradient, Gradient};
 use std::collections::HashMap;
 
@@ -5,25 +8,39 @@ use gloo::{
     console,
     storage::{LocalStorage, Storage},
 };
+use js_sys::Array;
 use serde::{Deserialize, Serialize};
 use serde_json::to_string;
-use web_sys::HtmlInputElement;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Blob, BlobPropertyBag, HtmlAnchorElement, HtmlInputElement, HtmlSelectElement,
+    HtmlTextAreaElement, Url,
+};
+use ws::Connection;
 use yew::{html::Scope, prelude::*};
 
-#[derive(Debug, Serialize, Deserialize)]
-struct State {
-    players: Vec<Player>,
-    scores: Vec<Vec<Score>>,
-    is_in_progress: bool,
-    first_to: u8,
-    negative_size: u8,
-    deck_size: u8,
+/// Which stage of a game the table is in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GamePhase {
+    Setup,
+    Entering,
+    GameOver,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct State {
+    pub(crate) players: Vec<Player>,
+    pub(crate) scores: Vec<Vec<Score>>,
+    phase: GamePhase,
+    pub(crate) first_to: u8,
+    pub(crate) negative_size: u8,
+    pub(crate) deck_size: u8,
 }
 
 type Player = String;
 #[derive(Debug, Serialize, Deserialize, Clone)]
-struct Score {
-    val: Option<i8>,
+pub(crate) struct Score {
+    pub(crate) val: Option<i8>,
     is_editing: bool,
 }
 
@@ -32,6 +49,38 @@ pub struct App {
     refs: HashMap<String, NodeRef>,
     leaderboard: Leaderboard,
     gradient: Gradient,
+    import_ref: NodeRef,
+    room_ref: NodeRef,
+    connection: Option<Connection>,
+    is_spectator: bool,
+    history: Vec<HistoryFrame>,
+    redo_stack: Vec<HistoryFrame>,
+    stats: HashMap<String, PlayerStats>,
+    stats_recorded: bool,
+    show_stats: bool,
+    win_odds: projection::WinOdds,
+}
+
+/// How many past states `Undo` can step back through.
+const MAX_HISTORY: usize = 50;
+
+/// A snapshotted `State` paired with whether *that* state had already been
+/// recorded into `PlayerStats`. Carrying `stats_recorded` alongside the state
+/// (rather than as a single bare flag on `App`) is what lets `Redo` restore a
+/// previously-recorded `GameOver` without double-counting it.
+type HistoryFrame = (State, bool);
+
+/// Pops the most recent frame off `from`, pushing `current` onto `to` in its
+/// place. `Undo` calls this with `(history, redo_stack)`; `Redo` calls it
+/// with the two reversed.
+fn step_history(
+    current: HistoryFrame,
+    from: &mut Vec<HistoryFrame>,
+    to: &mut Vec<HistoryFrame>,
+) -> Option<HistoryFrame> {
+    let frame = from.pop()?;
+    to.push(current);
+    Some(frame)
 }
 
 type Leaderboard = Vec<usize>;
@@ -41,13 +90,21 @@ impl State {
         Self {
             players: Vec::new(),
             scores: Vec::new(),
-            is_in_progress: false,
+            phase: GamePhase::Setup,
             first_to: 100, // the game ends when a player hits this number
             negative_size: 13,
             deck_size: 52,
         }
     }
 
+    /// Re-derives `phase` from the scores once entry for a round completes.
+    /// A no-op once the game is over or before it has started.
+    fn sync_phase(&mut self) {
+        if self.phase == GamePhase::Entering && self.is_game_over() {
+            self.phase = GamePhase::GameOver;
+        }
+    }
+
     fn next_round(&mut self) {
         let mut round: Vec<Score> = std::iter::repeat(Score {
             val: None,
@@ -125,6 +182,101 @@ impl State {
 }
 
 const KEY: &str = "yew.nertzpro.self";
+const STATS_KEY: &str = "yew.nertzpro.stats";
+
+/// Cross-game totals for one player, keyed by their normalized name.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct PlayerStats {
+    games_played: u32,
+    wins: u32,
+    podium_finishes: u32,
+    best_round: Option<i8>,
+    worst_round: Option<i8>,
+    round_sum: i64,
+    round_count: u32,
+}
+
+impl PlayerStats {
+    fn average_round(&self) -> f64 {
+        if self.round_count == 0 {
+            0.0
+        } else {
+            self.round_sum as f64 / self.round_count as f64
+        }
+    }
+}
+
+/// Folds case/whitespace variants of a name ("Sam", " sam ") into one stats key.
+fn normalize_player_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Whether `name` normalizes to the same stats key as a player already in `players`.
+fn is_duplicate_player_name(players: &[Player], name: &str) -> bool {
+    let key = normalize_player_name(name);
+    players
+        .iter()
+        .any(|existing| normalize_player_name(existing) == key)
+}
+
+/// Attributes a finished game's placements and per-round scores onto each
+/// player's running totals.
+fn record_game_stats(
+    stats: &mut HashMap<String, PlayerStats>,
+    state: &State,
+    leaderboard: &Leaderboard,
+) {
+    for (place, &player_idx) in leaderboard.iter().enumerate() {
+        let name = normalize_player_name(&state.players[player_idx]);
+        let entry = stats.entry(name).or_default();
+        entry.games_played += 1;
+        if place == 0 {
+            entry.wins += 1;
+        }
+        if place < 3 {
+            entry.podium_finishes += 1;
+        }
+        for round in &state.scores {
+            if let Some(val) = round[player_idx].val {
+                entry.round_sum += val as i64;
+                entry.round_count += 1;
+                entry.best_round = Some(entry.best_round.map_or(val, |best| best.max(val)));
+                entry.worst_round = Some(entry.worst_round.map_or(val, |worst| worst.min(val)));
+            }
+        }
+    }
+}
+
+/// A named rule set offered in the setup panel.
+struct RulePreset {
+    name: &'static str,
+    first_to: u8,
+    negative_size: u8,
+    deck_size: u8,
+}
+
+const RULE_PRESETS: &[RulePreset] = &[
+    RulePreset {
+        name: "Standard (52-card Nertz)",
+        first_to: 100,
+        negative_size: 13,
+        deck_size: 52,
+    },
+    RulePreset {
+        name: "Double Deck",
+        first_to: 200,
+        negative_size: 26,
+        deck_size: 104,
+    },
+];
+
+/// Clamps `negative_size`/`deck_size` so the legal score range they imply
+/// (`-negative_size..=deck_size-negative_size`) fits in `i8`, same as `Score::val`.
+fn clamp_rules(negative_size: u8, deck_size: u8) -> (u8, u8) {
+    let negative_size = negative_size.min(i8::MAX as u8 + 1);
+    let deck_size = deck_size.min(negative_size.saturating_add(i8::MAX as u8));
+    (negative_size, deck_size)
+}
 
 pub enum AppMsg {
     ScoreEnter(usize, usize, i8),
@@ -133,6 +285,105 @@ pub enum AppMsg {
     GameStart,
     PlayerAdd(String),
     PlayerRemove(usize),
+    GameExport,
+    GameImport(String),
+    RoomJoin(String, bool),
+    RemoteStateReceived(State),
+    ConnectionLost,
+    Undo,
+    Redo,
+    RulesChange {
+        first_to: u8,
+        negative_size: u8,
+        deck_size: u8,
+    },
+    ToggleStats,
+}
+
+/// Checks that a `State` is internally consistent before it replaces the live
+/// game: every round must have one score per player, within the legal range.
+/// Used both for a deserialized import and for a `State` broadcast by a peer.
+fn validate_imported_state(state: &State) -> Result<(), String> {
+    let width = state.players.len();
+    if state.scores.iter().any(|round| round.len() != width) {
+        return Err(format!(
+            "every round must have exactly {} scores, one per player",
+            width
+        ));
+    }
+
+    let min = -(state.negative_size as i16);
+    let max = state.deck_size as i16 - state.negative_size as i16;
+    let in_range = state.scores.iter().flatten().all(|score| {
+        score.val.map_or(true, |val| {
+            let val = val as i16;
+            val >= min && val <= max
+        })
+    });
+    if !in_range {
+        return Err(format!("score values must fall within {}..={}", min, max));
+    }
+
+    Ok(())
+}
+
+fn import_state(json: &str) -> Result<State, String> {
+    let state: State = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    validate_imported_state(&state)?;
+    Ok(state)
+}
+
+/// Whether `msg` is a local mutation that should be broadcast to the room,
+/// as opposed to one that only reflects what a peer already told us.
+fn is_local_mutation(msg: &AppMsg) -> bool {
+    !matches!(
+        msg,
+        AppMsg::RemoteStateReceived(_)
+            | AppMsg::ConnectionLost
+            | AppMsg::RoomJoin(_, _)
+            | AppMsg::ToggleStats
+    )
+}
+
+/// Whether `msg` represents a round actually played on this device, as
+/// opposed to a finished game arriving pre-made via import or from a peer.
+fn is_locally_played_mutation(msg: &AppMsg) -> bool {
+    !matches!(msg, AppMsg::RemoteStateReceived(_) | AppMsg::GameImport(_))
+}
+
+/// Whether `msg` changes `State` in a way worth a history entry. `GameImport`
+/// and `PlayerAdd` are handled separately (see their arms in `update`) since
+/// a malformed paste or a rejected duplicate name must not snapshot at all.
+fn is_undoable_mutation(msg: &AppMsg) -> bool {
+    matches!(
+        msg,
+        AppMsg::ScoreEnter(..) | AppMsg::GameNew | AppMsg::GameStart | AppMsg::PlayerRemove(_)
+    )
+}
+
+fn trigger_download(filename: &str, contents: &str) {
+    let window = web_sys::window().expect("no global window");
+    let document = window.document().expect("window has no document");
+
+    let parts = Array::new();
+    parts.push(&JsValue::from_str(contents));
+    let blob = Blob::new_with_str_sequence_and_options(
+        &parts,
+        BlobPropertyBag::new().type_("application/json"),
+    )
+    .expect("failed to build export blob");
+    let url = Url::create_object_url_with_blob(&blob).expect("failed to create object url");
+
+    let anchor: HtmlAnchorElement = document
+        .create_element("a")
+        .expect("failed to create anchor")
+        .dyn_into()
+        .expect("created element was not an anchor");
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    Url::revoke_object_url(&url).expect("failed to revoke object url");
 }
 
 impl App {
@@ -201,6 +452,11 @@ impl App {
         html! {
             <div>
                 {sum}
+                {if let Some(&odds) = self.win_odds.get(idx) {
+                    html! { <span class="odds">{format!(" ({:.0}%)", odds * 100.0)}</span> }
+                } else {
+                    html! {}
+                }}
             </div>
         }
     }
@@ -233,6 +489,79 @@ impl App {
         current_name.to_string()
     }
 
+    fn view_rules(&self, link: &Scope<Self>) -> Html {
+        let first_to = self.state.first_to;
+        let negative_size = self.state.negative_size;
+        let deck_size = self.state.deck_size;
+
+        let matched_preset = RULE_PRESETS.iter().position(|preset| {
+            preset.first_to == first_to
+                && preset.negative_size == negative_size
+                && preset.deck_size == deck_size
+        });
+
+        let onchange_preset = link.batch_callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let idx: usize = select.value().parse().ok()?;
+            let preset = RULE_PRESETS.get(idx)?;
+            Some(AppMsg::RulesChange {
+                first_to: preset.first_to,
+                negative_size: preset.negative_size,
+                deck_size: preset.deck_size,
+            })
+        });
+        let onchange_first_to = link.batch_callback(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let first_to = input.value().parse().ok()?;
+            Some(AppMsg::RulesChange {
+                first_to,
+                negative_size,
+                deck_size,
+            })
+        });
+        let onchange_negative_size = link.batch_callback(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let negative_size = input.value().parse().ok()?;
+            Some(AppMsg::RulesChange {
+                first_to,
+                negative_size,
+                deck_size,
+            })
+        });
+        let onchange_deck_size = link.batch_callback(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let deck_size = input.value().parse().ok()?;
+            Some(AppMsg::RulesChange {
+                first_to,
+                negative_size,
+                deck_size,
+            })
+        });
+
+        html! {
+            <div class="rules">
+                <select onchange={onchange_preset}>
+                    { for RULE_PRESETS.iter().enumerate().map(|(idx, preset)| html! {
+                        <option value={idx.to_string()} selected={matched_preset == Some(idx)}>{preset.name}</option>
+                    }) }
+                    <option value="custom" selected={matched_preset.is_none()}>{"Custom"}</option>
+                </select>
+                <label>
+                    {"First to"}
+                    <input type="number" value={first_to.to_string()} onchange={onchange_first_to}/>
+                </label>
+                <label>
+                    {"Negative pile size"}
+                    <input type="number" value={negative_size.to_string()} onchange={onchange_negative_size}/>
+                </label>
+                <label>
+                    {"Deck size"}
+                    <input type="number" value={deck_size.to_string()} onchange={onchange_deck_size}/>
+                </label>
+            </div>
+        }
+    }
+
     fn get_next_empty(&mut self) -> Option<&mut Score> {
         self.state
             .scores
@@ -240,6 +569,52 @@ impl App {
             .rev()
             .find_map(|round| round.iter_mut().find(|score| score.val.is_none()))
     }
+
+    /// Pushes the current state onto `history` and drops any redo path, since
+    /// a new undoable action invalidates whatever was undone before it.
+    fn snapshot_history(&mut self) {
+        self.history.push((self.state.clone(), self.stats_recorded));
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    fn view_stats(&self, ctx: &Context<Self>) -> Html {
+        let mut rows: Vec<(&String, &PlayerStats)> = self.stats.iter().collect();
+        rows.sort_by(|(_, a), (_, b)| b.wins.cmp(&a.wins));
+
+        html! {
+            <div class="container">
+                <img id="logo" src="static/logo.png" alt="NERTS.PRO"/>
+                <table class="stats">
+                    <tr>
+                        <th>{"player"}</th>
+                        <th>{"games"}</th>
+                        <th>{"wins"}</th>
+                        <th>{"podiums"}</th>
+                        <th>{"best round"}</th>
+                        <th>{"worst round"}</th>
+                        <th>{"avg round"}</th>
+                    </tr>
+                    { for rows.iter().map(|(name, stats)| html! {
+                        <tr>
+                            <td>{name}</td>
+                            <td>{stats.games_played}</td>
+                            <td>{stats.wins}</td>
+                            <td>{stats.podium_finishes}</td>
+                            <td>{stats.best_round.map_or("--".to_string(), |v| v.to_string())}</td>
+                            <td>{stats.worst_round.map_or("--".to_string(), |v| v.to_string())}</td>
+                            <td>{format!("{:.1}", stats.average_round())}</td>
+                        </tr>
+                    }) }
+                </table>
+                <div class="button">
+                    <button onclick={ctx.link().callback(move |_| AppMsg::ToggleStats)}>{"BACK"}</button>
+                </div>
+            </div>
+        }
+    }
 }
 
 fn make_refs(state: &State) -> HashMap<String, NodeRef> {
@@ -259,6 +634,7 @@ impl Component for App {
 
     fn create(_ctx: &Context<Self>) -> Self {
         let state = LocalStorage::get(KEY).unwrap_or_else(|_| State::new());
+        let stats = LocalStorage::get(STATS_KEY).unwrap_or_default();
 
         let refs = make_refs(&state);
         let leaderboard = state.get_leader_board();
@@ -277,11 +653,21 @@ impl Component for App {
             refs,
             leaderboard,
             gradient,
+            import_ref: NodeRef::default(),
+            room_ref: NodeRef::default(),
+            connection: None,
+            is_spectator: false,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            stats,
+            stats_recorded: false,
+            show_stats: false,
+            win_odds: Vec::new(),
         }
     }
 
     fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
-        if self.state.is_in_progress {
+        if self.state.phase != GamePhase::Setup {
             let node_ref = self.refs.get(&self.get_focused()).unwrap();
 
             if let Some(input) = node_ref.cast::<HtmlInputElement>() {
@@ -290,7 +676,14 @@ impl Component for App {
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let should_broadcast = is_local_mutation(&msg);
+        let should_record_stats = is_locally_played_mutation(&msg);
+
+        if is_undoable_mutation(&msg) {
+            self.snapshot_history();
+        }
+
         match msg {
             AppMsg::ScoreEnter(round, player, score) => {
                 self.state.scores[round][player] = Score {
@@ -299,9 +692,13 @@ impl Component for App {
                 };
                 if let Some(score) = self.get_next_empty() {
                     score.is_editing = true;
-                } else if !self.state.is_game_over() {
-                    self.next_round();
+                } else {
+                    self.state.sync_phase();
+                    if self.state.phase == GamePhase::Entering {
+                        self.next_round();
+                    }
                 }
+                self.win_odds = projection::win_odds(&self.state);
             }
             AppMsg::ScoreEdit(round_idx_edit, player_idx_edit) => self
                 .state
@@ -320,32 +717,141 @@ impl Component for App {
                 }),
             AppMsg::GameNew => {
                 let players = self.state.players.clone();
+                let first_to = self.state.first_to;
+                let negative_size = self.state.negative_size;
+                let deck_size = self.state.deck_size;
                 self.state = State::new();
                 self.state.players = players;
+                self.state.first_to = first_to;
+                self.state.negative_size = negative_size;
+                self.state.deck_size = deck_size;
+                self.stats_recorded = false;
+                self.win_odds = projection::win_odds(&self.state);
             }
             AppMsg::GameStart => {
-                self.state.is_in_progress = true;
+                self.state.phase = GamePhase::Entering;
                 self.next_round();
+                self.stats_recorded = false;
+                self.win_odds = projection::win_odds(&self.state);
+            }
+            AppMsg::PlayerAdd(name) => {
+                if is_duplicate_player_name(&self.state.players, &name) {
+                    console::log!(to_string(&format!(
+                        "a player named \"{}\" is already in this game",
+                        name
+                    ))
+                    .unwrap());
+                    return false;
+                }
+                self.snapshot_history();
+                self.state.players.push(name);
+                self.win_odds = projection::win_odds(&self.state);
             }
-            AppMsg::PlayerAdd(name) => self.state.players.push(name),
             AppMsg::PlayerRemove(idx) => {
                 self.state.players.remove(idx);
+                self.win_odds = projection::win_odds(&self.state);
+            }
+            AppMsg::GameExport => {
+                let json = to_string(&self.state).expect("failed to serialize state");
+                trigger_download("nertz-game.json", &json);
+                return false;
+            }
+            AppMsg::GameImport(json) => match import_state(&json) {
+                Ok(state) => {
+                    self.snapshot_history();
+                    self.refs = make_refs(&state);
+                    self.state = state;
+                    self.win_odds = projection::win_odds(&self.state);
+                }
+                Err(err) => console::log!(to_string(&err).unwrap()),
+            },
+            AppMsg::RoomJoin(room_code, spectator) => {
+                let on_message = ctx.link().callback(|msg| msg);
+                self.connection = Some(Connection::join(&room_code, spectator, on_message));
+                self.is_spectator = spectator;
+            }
+            AppMsg::RemoteStateReceived(state) => match validate_imported_state(&state) {
+                Ok(()) => {
+                    self.refs = make_refs(&state);
+                    self.state = state;
+                    self.win_odds = projection::win_odds(&self.state);
+                }
+                Err(err) => console::log!(to_string(&err).unwrap()),
+            },
+            AppMsg::ConnectionLost => {
+                self.connection = None;
+            }
+            AppMsg::Undo => {
+                let current = (self.state.clone(), self.stats_recorded);
+                if let Some((prev, prev_stats_recorded)) =
+                    step_history(current, &mut self.history, &mut self.redo_stack)
+                {
+                    self.refs = make_refs(&prev);
+                    self.state = prev;
+                    self.stats_recorded = prev_stats_recorded;
+                    self.win_odds = projection::win_odds(&self.state);
+                } else {
+                    return false;
+                }
+            }
+            AppMsg::Redo => {
+                let current = (self.state.clone(), self.stats_recorded);
+                if let Some((next, next_stats_recorded)) =
+                    step_history(current, &mut self.redo_stack, &mut self.history)
+                {
+                    self.refs = make_refs(&next);
+                    self.state = next;
+                    self.stats_recorded = next_stats_recorded;
+                    self.win_odds = projection::win_odds(&self.state);
+                } else {
+                    return false;
+                }
+            }
+            AppMsg::RulesChange {
+                first_to,
+                negative_size,
+                deck_size,
+            } => {
+                let (negative_size, deck_size) = clamp_rules(negative_size, deck_size);
+                self.state.first_to = first_to;
+                self.state.negative_size = negative_size;
+                self.state.deck_size = deck_size;
+                self.win_odds = projection::win_odds(&self.state);
+            }
+            AppMsg::ToggleStats => {
+                self.show_stats = !self.show_stats;
             }
         }
         self.leaderboard = self.state.get_leader_board();
 
+        if should_record_stats && self.state.phase == GamePhase::GameOver && !self.stats_recorded {
+            record_game_stats(&mut self.stats, &self.state, &self.leaderboard);
+            LocalStorage::set(STATS_KEY, &self.stats).expect("failed to set stats");
+            self.stats_recorded = true;
+        }
+
+        if should_broadcast && !self.is_spectator {
+            if let Some(connection) = &self.connection {
+                connection.broadcast(&self.state);
+            }
+        }
+
         LocalStorage::set(KEY, &self.state).expect("failed to set");
         true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.show_stats {
+            return self.view_stats(ctx);
+        }
+
         console::log!(to_string(&format!("{:?}", self.state)).unwrap());
-        let is_game_over = self.state.is_game_over();
+        let is_game_over = self.state.phase == GamePhase::GameOver;
 
         html! {
             <div class="container">
                 <img id="logo" src="static/logo.png" alt="NERTS.PRO"/>
-                {if self.state.is_in_progress {
+                {if self.state.phase != GamePhase::Setup {
                     html! {
                         <div>
                 <table class="scores">
@@ -385,23 +891,32 @@ impl Component for App {
                         let key = format!("{}_{}", round_idx, player_idx);
                         let node_ref = self.refs.get(&key).unwrap();
 
+                        let is_spectator = self.is_spectator;
+
                         let onkeypress = ctx.link().batch_callback(move |e: KeyboardEvent| {
+                            if is_spectator {
+                                return None;
+                            }
                             if e.key() == "Enter" {
                                 let input: HtmlInputElement = e.target_unchecked_into();
-                                let val = input.value().parse::<i8>().unwrap();
+                                let val = input.value().parse::<i8>().ok()?;
                                 Some(AppMsg::ScoreEnter(round_idx, player_idx, val))
                             } else {
                                 None
                             }
                         });
 
-                        let onclick = ctx.link().callback(move |_| {
-                            AppMsg::ScoreEdit(round_idx, player_idx)
+                        let onclick = ctx.link().batch_callback(move |_| {
+                            if is_spectator {
+                                None
+                            } else {
+                                Some(AppMsg::ScoreEdit(round_idx, player_idx))
+                            }
                         });
 
                         html! {
                             <td {onclick}>
-                            {if score.is_editing {
+                            {if score.is_editing && !self.is_spectator {
                                 html! {
                                     <input ref={node_ref} {onkeypress} value={if let Some(s) = score.val { s.to_string() } else { String::new() }} type="number"/>
                                 }
@@ -432,7 +947,11 @@ impl Component for App {
 
                 </table>
                 <div class="button">
-                    <button onclick={ctx.link().callback(move |_| AppMsg::GameNew)}>{"NEW GAME"}</button>
+                    <button disabled={self.is_spectator} onclick={ctx.link().callback(move |_| AppMsg::GameNew)}>{"NEW GAME"}</button>
+                    <button onclick={ctx.link().callback(move |_| AppMsg::GameExport)}>{"EXPORT GAME"}</button>
+                    <button disabled={self.is_spectator || self.history.is_empty()} onclick={ctx.link().callback(move |_| AppMsg::Undo)}>{"UNDO"}</button>
+                    <button disabled={self.is_spectator || self.redo_stack.is_empty()} onclick={ctx.link().callback(move |_| AppMsg::Redo)}>{"REDO"}</button>
+                    <button onclick={ctx.link().callback(move |_| AppMsg::ToggleStats)}>{"STATS"}</button>
                 </div>
                 </div>
 
@@ -441,6 +960,30 @@ impl Component for App {
 
                 } else {
                     let disabled = self.state.players.len() < 2;
+                    let import_ref = self.import_ref.clone();
+                    let onclick_import = ctx.link().callback(move |_| {
+                        let json = import_ref
+                            .cast::<HtmlTextAreaElement>()
+                            .map(|textarea| textarea.value())
+                            .unwrap_or_default();
+                        AppMsg::GameImport(json)
+                    });
+
+                    let room_code = move |room_ref: NodeRef| {
+                        room_ref
+                            .cast::<HtmlInputElement>()
+                            .map(|input| input.value())
+                            .unwrap_or_default()
+                    };
+                    let join_room_ref = self.room_ref.clone();
+                    let onclick_join = ctx.link().callback(move |_| {
+                        AppMsg::RoomJoin(room_code(join_room_ref.clone()), false)
+                    });
+                    let spectate_room_ref = self.room_ref.clone();
+                    let onclick_spectate = ctx.link().callback(move |_| {
+                        AppMsg::RoomJoin(room_code(spectate_room_ref.clone()), true)
+                    });
+
                     html! {
                         <div>
 
@@ -448,9 +991,31 @@ impl Component for App {
                                 { for self.state.players.iter().enumerate().map(|(idx, player)| self.view_player(idx, player, ctx.link()))}
                             </ul>
                             {self.view_input(ctx.link())}
+                            {self.view_rules(ctx.link())}
 
                             <div class="button">
                                 <button {disabled} onclick={ctx.link().callback(move |_| AppMsg::GameStart)}>{"START GAME"}</button>
+                                <button onclick={ctx.link().callback(move |_| AppMsg::ToggleStats)}>{"STATS"}</button>
+                            </div>
+
+                            <div class="room">
+                                {if self.connection.is_some() {
+                                    let role = if self.is_spectator { "spectating" } else { "connected" };
+                                    html! { <span class="room-status">{format!("room: {}", role)}</span> }
+                                } else {
+                                    html! {
+                                        <>
+                                            <input ref={self.room_ref.clone()} class="room-code" placeholder="Room code"/>
+                                            <button onclick={onclick_join}>{"JOIN ROOM"}</button>
+                                            <button onclick={onclick_spectate}>{"SPECTATE"}</button>
+                                        </>
+                                    }
+                                }}
+                            </div>
+
+                            <div class="import-export">
+                                <textarea ref={self.import_ref.clone()} class="import" placeholder="Paste exported game JSON here"></textarea>
+                                <button onclick={onclick_import}>{"IMPORT GAME"}</button>
                             </div>
                         </div>
                     }
@@ -464,3 +1029,143 @@ impl Component for App {
 fn main() {
     yew::Renderer::<App>::new().render();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(val: Option<i8>) -> Score {
+        Score {
+            val,
+            is_editing: false,
+        }
+    }
+
+    #[test]
+    fn validate_imported_state_accepts_consistent_scores() {
+        let state = State {
+            players: vec!["a".to_string(), "b".to_string()],
+            scores: vec![vec![score(Some(3)), score(Some(-2))]],
+            phase: GamePhase::Entering,
+            first_to: 100,
+            negative_size: 13,
+            deck_size: 52,
+        };
+        assert!(validate_imported_state(&state).is_ok());
+    }
+
+    #[test]
+    fn validate_imported_state_rejects_round_width_mismatch() {
+        let state = State {
+            players: vec!["a".to_string(), "b".to_string()],
+            scores: vec![vec![score(Some(3))]],
+            phase: GamePhase::Entering,
+            first_to: 100,
+            negative_size: 13,
+            deck_size: 52,
+        };
+        assert!(validate_imported_state(&state).is_err());
+    }
+
+    #[test]
+    fn validate_imported_state_rejects_out_of_range_score() {
+        let state = State {
+            players: vec!["a".to_string()],
+            scores: vec![vec![score(Some(100))]],
+            phase: GamePhase::Entering,
+            first_to: 100,
+            negative_size: 13,
+            deck_size: 52,
+        };
+        assert!(validate_imported_state(&state).is_err());
+    }
+
+    #[test]
+    fn import_state_rejects_malformed_json() {
+        assert!(import_state("not json").is_err());
+    }
+
+    fn finished_state(players: &[&str], rounds: &[&[i8]]) -> State {
+        State {
+            players: players.iter().map(|p| p.to_string()).collect(),
+            scores: rounds
+                .iter()
+                .map(|round| round.iter().map(|&v| score(Some(v))).collect())
+                .collect(),
+            phase: GamePhase::GameOver,
+            first_to: 100,
+            negative_size: 13,
+            deck_size: 52,
+        }
+    }
+
+    #[test]
+    fn normalize_player_name_folds_case_and_whitespace() {
+        assert_eq!(normalize_player_name(" Sam "), normalize_player_name("sam"));
+    }
+
+    #[test]
+    fn is_duplicate_player_name_catches_case_and_whitespace_variants() {
+        let players = vec!["Alice".to_string()];
+        assert!(is_duplicate_player_name(&players, " alice "));
+        assert!(!is_duplicate_player_name(&players, "Bob"));
+    }
+
+    #[test]
+    fn record_game_stats_credits_winner_and_round_extremes() {
+        let state = finished_state(&["Alice", "Bob"], &[&[10, -2], &[5, 3]]);
+        let leaderboard = state.get_leader_board();
+
+        let mut stats = HashMap::new();
+        record_game_stats(&mut stats, &state, &leaderboard);
+
+        let alice = &stats[&normalize_player_name("Alice")];
+        assert_eq!(alice.games_played, 1);
+        assert_eq!(alice.wins, 1);
+        assert_eq!(alice.podium_finishes, 1);
+        assert_eq!(alice.best_round, Some(10));
+        assert_eq!(alice.worst_round, Some(5));
+
+        let bob = &stats[&normalize_player_name("Bob")];
+        assert_eq!(bob.wins, 0);
+        assert_eq!(bob.best_round, Some(3));
+        assert_eq!(bob.worst_round, Some(-2));
+    }
+
+    #[test]
+    fn record_game_stats_accumulates_across_games() {
+        let state = finished_state(&["Alice", "Bob"], &[&[10, -2]]);
+        let leaderboard = state.get_leader_board();
+
+        let mut stats = HashMap::new();
+        record_game_stats(&mut stats, &state, &leaderboard);
+        record_game_stats(&mut stats, &state, &leaderboard);
+
+        assert_eq!(stats[&normalize_player_name("Alice")].games_played, 2);
+        assert_eq!(stats[&normalize_player_name("Alice")].wins, 2);
+    }
+
+    /// Regression test: `stats_recorded` must travel with the snapshotted
+    /// state through `step_history`, not live as a bare flag Redo leaves stale.
+    #[test]
+    fn history_frame_restores_stats_recorded_through_undo_and_redo() {
+        let mut entering = finished_state(&["Alice", "Bob"], &[]);
+        entering.phase = GamePhase::Entering;
+        let mut history: Vec<HistoryFrame> = vec![(entering, false)];
+        let mut redo_stack: Vec<HistoryFrame> = Vec::new();
+
+        let state = finished_state(&["Alice", "Bob"], &[&[10, -2]]);
+        let stats_recorded = true;
+
+        let (state, stats_recorded) =
+            step_history((state, stats_recorded), &mut history, &mut redo_stack)
+                .expect("history has a frame to undo to");
+        assert!(!stats_recorded);
+
+        let (state, stats_recorded) =
+            step_history((state, stats_recorded), &mut redo_stack, &mut history)
+                .expect("redo_stack has a frame to redo to");
+        assert!(stats_recorded);
+        assert_eq!(state.phase, GamePhase::GameOver);
+    }
+}
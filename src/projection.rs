@@ -0,0 +1,217 @@
+use rand::Rng;
+
+use crate::{GamePhase, State};
+
+/// Already-entered rounds a player needs before their own score history is
+/// drawn from instead of the uniform fallback distribution.
+const MIN_SAMPLES: usize = 3;
+
+/// Kept small since this runs synchronously on the UI thread on every score entry.
+const PLAYOUTS: u32 = 2_000;
+
+/// Abandons a playout that blows through this many rounds without a unique
+/// winner, so an all-non-positive empirical distribution can't loop forever.
+const MAX_ROUNDS_PER_PLAYOUT: u32 = 300;
+
+/// Monte-Carlo win-probability estimate, indexed the same way as `State::players`.
+pub(crate) type WinOdds = Vec<f64>;
+
+/// Draws one simulated round of scores, one per player, adding each draw
+/// straight into `totals` rather than allocating a fresh `Vec` per call,
+/// since this runs up to `PLAYOUTS * MAX_ROUNDS_PER_PLAYOUT` times per
+/// keystroke. `min`/`max` are clamped to `i8` first, since custom rules can
+/// set `deck_size`/`negative_size` wide enough to otherwise wrap on cast.
+fn sample_round(min: i16, max: i16, samples: &[Vec<i8>], rng: &mut impl Rng, totals: &mut [i64]) {
+    let min = min.clamp(i8::MIN as i16, i8::MAX as i16);
+    let max = max.clamp(i8::MIN as i16, i8::MAX as i16);
+    for (idx, player_samples) in samples.iter().enumerate() {
+        let val = if player_samples.len() >= MIN_SAMPLES {
+            player_samples[rng.gen_range(0..player_samples.len())]
+        } else {
+            rng.gen_range(min..=max) as i8
+        };
+        totals[idx] += i64::from(val);
+    }
+}
+
+/// The best a single simulated round could add to any one player's total. If
+/// this is non-positive across every player, no amount of simulated rounds
+/// can ever reach `first_to`, so `win_odds` can skip the playouts entirely.
+fn max_possible_gain(max: i16, samples: &[Vec<i8>]) -> i8 {
+    let max = max.clamp(i8::MIN as i16, i8::MAX as i16) as i8;
+    samples
+        .iter()
+        .map(|player_samples| {
+            if player_samples.len() >= MIN_SAMPLES {
+                player_samples.iter().copied().max().unwrap_or(max)
+            } else {
+                max
+            }
+        })
+        .max()
+        .unwrap_or(max)
+}
+
+/// The unique winner in `totals`, if any: someone at or above `first_to` with
+/// nobody else tying them for the lead.
+fn unique_winner(totals: &[i64], first_to: u8) -> Option<usize> {
+    let &max = totals.iter().max()?;
+    if max < first_to as i64 {
+        return None;
+    }
+
+    let mut leaders = totals.iter().enumerate().filter(|(_, &total)| total == max);
+    let winner = leaders.next()?.0;
+    if leaders.next().is_some() {
+        None
+    } else {
+        Some(winner)
+    }
+}
+
+/// Estimates each player's probability of winning from the current scores via
+/// `PLAYOUTS` simulated continuations.
+pub(crate) fn win_odds(state: &State) -> WinOdds {
+    let num_players = state.players.len();
+    if num_players == 0 || state.phase == GamePhase::Setup {
+        return Vec::new();
+    }
+
+    let mut samples: Vec<Vec<i8>> = vec![Vec::new(); num_players];
+    for round in &state.scores {
+        for (idx, score) in round.iter().enumerate() {
+            if let Some(val) = score.val {
+                samples[idx].push(val);
+            }
+        }
+    }
+
+    let current_totals: Vec<i64> = (0..num_players)
+        .map(|idx| {
+            state
+                .scores
+                .iter()
+                .filter_map(|round| round[idx].val)
+                .map(i64::from)
+                .sum()
+        })
+        .collect();
+
+    let min = -(state.negative_size as i16);
+    let max = state.deck_size as i16 - state.negative_size as i16;
+
+    if max_possible_gain(max, &samples) <= 0 {
+        return vec![0.0; num_players];
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut wins = vec![0u32; num_players];
+    let mut completed_playouts = 0u32;
+
+    for _ in 0..PLAYOUTS {
+        let mut totals = current_totals.clone();
+        let mut rounds_left = MAX_ROUNDS_PER_PLAYOUT;
+        loop {
+            if let Some(winner) = unique_winner(&totals, state.first_to) {
+                wins[winner] += 1;
+                completed_playouts += 1;
+                break;
+            }
+            if rounds_left == 0 {
+                break;
+            }
+            rounds_left -= 1;
+            sample_round(min, max, &samples, &mut rng, &mut totals);
+        }
+    }
+
+    if completed_playouts == 0 {
+        return vec![0.0; num_players];
+    }
+
+    wins.iter()
+        .map(|&w| f64::from(w) / f64::from(completed_playouts))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Score;
+
+    fn score(val: Option<i8>) -> Score {
+        Score {
+            val,
+            is_editing: false,
+        }
+    }
+
+    fn state_with_rounds(
+        num_players: usize,
+        rounds: &[&[i8]],
+        first_to: u8,
+        negative_size: u8,
+        deck_size: u8,
+    ) -> State {
+        State {
+            players: (0..num_players).map(|i| format!("p{i}")).collect(),
+            scores: rounds
+                .iter()
+                .map(|round| round.iter().map(|&v| score(Some(v))).collect())
+                .collect(),
+            phase: GamePhase::Entering,
+            first_to,
+            negative_size,
+            deck_size,
+        }
+    }
+
+    #[test]
+    fn unique_winner_requires_reaching_first_to() {
+        assert_eq!(unique_winner(&[50, 40], 100), None);
+    }
+
+    #[test]
+    fn unique_winner_rejects_ties() {
+        assert_eq!(unique_winner(&[100, 100], 100), None);
+    }
+
+    #[test]
+    fn unique_winner_picks_the_sole_leader() {
+        assert_eq!(unique_winner(&[100, 40], 100), Some(0));
+    }
+
+    #[test]
+    fn sample_round_clamps_out_of_range_rules_instead_of_wrapping() {
+        let samples: Vec<Vec<i8>> = vec![Vec::new()];
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let mut totals = vec![0i64];
+            sample_round(-200, 200, &samples, &mut rng, &mut totals);
+            assert!(totals[0] >= i8::MIN as i64 && totals[0] <= i8::MAX as i64);
+        }
+    }
+
+    #[test]
+    fn win_odds_is_empty_before_a_game_starts() {
+        let state = state_with_rounds(2, &[], 100, 13, 52);
+        let mut setup_state = state;
+        setup_state.phase = GamePhase::Setup;
+        assert!(win_odds(&setup_state).is_empty());
+    }
+
+    #[test]
+    fn win_odds_sums_to_one_across_players() {
+        let state = state_with_rounds(2, &[&[10, -2], &[5, 3]], 100, 13, 52);
+        let odds = win_odds(&state);
+        assert_eq!(odds.len(), 2);
+        assert!((odds.iter().sum::<f64>() - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn win_odds_terminates_on_an_all_non_positive_distribution() {
+        let state = state_with_rounds(2, &[&[0, -1], &[-1, 0], &[0, -1]], 100, 13, 52);
+        let odds = win_odds(&state);
+        assert_eq!(odds, vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,74 @@
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use gloo::console;
+use gloo_net::websocket::{futures::WebSocket, Message};
+use serde_json::to_string;
+use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
+
+use crate::{AppMsg, State};
+
+/// A live connection to a scoresheet room.
+pub struct Connection {
+    outgoing: mpsc::UnboundedSender<String>,
+}
+
+impl Connection {
+    /// Opens a websocket to `room_code` and forwards inbound state to `on_message`.
+    pub fn join(room_code: &str, spectator: bool, on_message: Callback<AppMsg>) -> Self {
+        let url = room_url(room_code, spectator);
+        let ws = WebSocket::open(&url).expect("failed to open websocket");
+        let (mut write, mut read) = ws.split();
+        let (outgoing, mut rx) = mpsc::unbounded::<String>();
+
+        spawn_local(async move {
+            while let Some(json) = rx.next().await {
+                if write.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        spawn_local(async move {
+            while let Some(msg) = read.next().await {
+                match msg {
+                    Ok(Message::Text(text)) => match serde_json::from_str::<State>(&text) {
+                        Ok(state) => on_message.emit(AppMsg::RemoteStateReceived(state)),
+                        Err(err) => console::log!(to_string(&err.to_string()).unwrap()),
+                    },
+                    Ok(Message::Bytes(_)) => {}
+                    Err(_) => break,
+                }
+            }
+            on_message.emit(AppMsg::ConnectionLost);
+        });
+
+        Self { outgoing }
+    }
+
+    /// Broadcasts the current game state to everyone else in the room.
+    pub fn broadcast(&self, state: &State) {
+        if let Ok(json) = serde_json::to_string(state) {
+            let _ = self.outgoing.unbounded_send(json);
+        }
+    }
+}
+
+fn room_url(room_code: &str, spectator: bool) -> String {
+    format!(
+        "wss://nertz.pro/ws/{}?spectator={spectator}",
+        percent_encode(room_code)
+    )
+}
+
+/// Percent-encodes `input` for safe use as a single URL path segment.
+fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}